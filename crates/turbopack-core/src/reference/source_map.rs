@@ -1,9 +1,159 @@
 use anyhow::Result;
 use turbo_tasks::{ValueToString, Vc};
-use turbo_tasks_fs::{FileSystemEntryType, FileSystemPath};
+use turbo_tasks_fs::{FileContent, FileSystemEntryType, FileSystemPath};
 
 use super::AssetReference;
-use crate::{file_source::FileSource, resolve::ResolveResult};
+use crate::{
+    file_source::FileSource,
+    resolve::ResolveResult,
+    source_map::{GenerateSourceMap, OptionSourceMap, SourceMap},
+};
+
+/// The target of a `//# sourceMappingURL=...` comment found at the tail of a generated asset.
+enum SourceMappingUrl {
+    /// A `data:` URL whose contents have already been decoded into a map, with no filesystem hop
+    /// required.
+    Inline(Vc<SourceMap>),
+    /// A path (relative to the asset carrying the comment) that should be resolved on disk.
+    Relative(Vc<FileSystemPath>),
+}
+
+/// The generated asset content is only ever searched this many trailing bytes for a
+/// `sourceMappingURL` comment, per the spec's expectation that the comment is the last line of
+/// the file. This also keeps the scan cheap for large bundles and avoids matching an incidental
+/// occurrence of the literal string earlier in the file (e.g. inside a string literal or embedded
+/// fixture).
+const SOURCE_MAPPING_URL_TAIL_BYTES: usize = 8192;
+
+/// Scans the tail of `from`'s content for a `//# sourceMappingURL=` (or legacy `//@`) comment and
+/// returns what it points at, if anything. Scanning is best-effort: a file with no such comment,
+/// one where the match isn't actually inside a `//` comment, or one that isn't valid UTF-8, simply
+/// yields `None`.
+async fn scan_source_mapping_url(from: Vc<FileSystemPath>) -> Result<Option<SourceMappingUrl>> {
+    let content = from.read().await?;
+    let FileContent::Content(file) = &*content else {
+        return Ok(None);
+    };
+    let Ok(text) = file.content().to_str() else {
+        return Ok(None);
+    };
+    let Some(url) = find_source_mapping_url(tail_str(text, SOURCE_MAPPING_URL_TAIL_BYTES)) else {
+        return Ok(None);
+    };
+
+    if let Some(encoded) = url.strip_prefix("data:application/json;base64,") {
+        if let Ok(bytes) = decode_base64(encoded.trim()) {
+            if let Ok(map) = sourcemap::SourceMap::from_slice(&bytes) {
+                return Ok(Some(SourceMappingUrl::Inline(
+                    SourceMap::new_regular(map).cell(),
+                )));
+            }
+        }
+        // A malformed inline map is no different from a missing one: trace without a map rather
+        // than failing resolution for the whole asset.
+        return Ok(None);
+    }
+    if let Some(encoded) = url.strip_prefix("data:application/json,") {
+        let decoded = decode_percent(encoded.trim());
+        return Ok(sourcemap::SourceMap::from_slice(decoded.as_bytes())
+            .ok()
+            .map(|map| SourceMappingUrl::Inline(SourceMap::new_regular(map).cell())));
+    }
+
+    Ok(Some(SourceMappingUrl::Relative(
+        from.parent().join(url.to_string()),
+    )))
+}
+
+/// Returns the last `max_bytes` of `text`, narrowed forwards (never widened backwards) to the next
+/// char boundary so the slice is always valid UTF-8 and never longer than `max_bytes`.
+fn tail_str(text: &str, max_bytes: usize) -> &str {
+    let mut start = text.len().saturating_sub(max_bytes);
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    &text[start..]
+}
+
+/// Finds the last `//# sourceMappingURL=`/`//@ sourceMappingURL=` (or `/*# ... */`/`/*@ ... */`
+/// block-comment) directive in `text` and returns the URL it points at. A bare occurrence of the
+/// string `sourceMappingURL=` that isn't actually preceded by one of those comment openers (e.g.
+/// one embedded in a string literal or template) is ignored.
+fn find_source_mapping_url(text: &str) -> Option<&str> {
+    let (prefix, rest) = text.rsplit_once("sourceMappingURL=")?;
+    let prefix = prefix.trim_end();
+    let is_comment = ["//#", "//@", "/*#", "/*@"]
+        .iter()
+        .any(|marker| prefix.ends_with(marker));
+    if !is_comment {
+        return None;
+    }
+
+    let line = rest.lines().next().unwrap_or(rest);
+    Some(line.trim_end_matches("*/").trim())
+}
+
+/// Decodes a standard-alphabet base64 string. Small and local rather than pulled in as a
+/// dependency, since this is the only place in the crate that needs it.
+fn decode_base64(input: &str) -> Result<Vec<u8>> {
+    fn value(byte: u8) -> Option<u8> {
+        match byte {
+            b'A'..=b'Z' => Some(byte - b'A'),
+            b'a'..=b'z' => Some(byte - b'a' + 26),
+            b'0'..=b'9' => Some(byte - b'0' + 52),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buf = 0u32;
+    let mut bits = 0u32;
+    for byte in input.bytes() {
+        if byte == b'=' || byte.is_ascii_whitespace() {
+            continue;
+        }
+        let Some(v) = value(byte) else {
+            anyhow::bail!("invalid base64 byte {byte:#x} in sourceMappingURL");
+        };
+        buf = (buf << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buf >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
+
+/// Decodes `%XX` percent-escapes, leaving any other byte untouched.
+///
+/// Operates on raw bytes throughout: `input` may contain multi-byte UTF-8 sequences after a `%`
+/// (e.g. `"%1é"`), and indexing by byte offset into the `&str` to grab the two hex digits would
+/// panic if that window landed mid-character. Working on `bytes` and validating each candidate
+/// pair as ASCII hex digits before parsing sidesteps that entirely.
+fn decode_percent(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let (hi, lo) = (bytes[i + 1], bytes[i + 2]);
+            if hi.is_ascii_hexdigit() && lo.is_ascii_hexdigit() {
+                // Safe to unwrap: both bytes were just confirmed to be ASCII hex digits.
+                let value = u8::from_str_radix(std::str::from_utf8(&[hi, lo]).unwrap(), 16)
+                    .expect("validated ascii hex digits");
+                out.push(value);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
 
 #[turbo_tasks::value]
 pub struct SourceMapReference {
@@ -22,14 +172,55 @@ impl SourceMapReference {
 #[turbo_tasks::value_impl]
 impl AssetReference for SourceMapReference {
     #[turbo_tasks::function]
-    async fn resolve_reference(&self) -> Vc<ResolveResult> {
+    async fn resolve_reference(&self) -> Result<Vc<ResolveResult>> {
+        let mut results = vec![];
+
+        // An inline (data:) map has no file to add to the resolve graph; only a map discovered
+        // at a relative path does.
+        if let Some(SourceMappingUrl::Relative(path)) = scan_source_mapping_url(self.from).await?
+        {
+            if let FileSystemEntryType::File = &*path.get_type().await? {
+                results.push(ResolveResult::asset(Vc::upcast(FileSource::new(path))).into());
+            }
+        }
+
         let file_type = self.file.get_type().await;
         if let Ok(file_type_result) = file_type.as_ref() {
             if let FileSystemEntryType::File = &**file_type_result {
-                return ResolveResult::asset(Vc::upcast(FileSource::new(self.file))).into();
+                results.push(ResolveResult::asset(Vc::upcast(FileSource::new(self.file))).into());
             }
         }
-        ResolveResult::unresolveable().into()
+
+        if results.is_empty() {
+            return Ok(ResolveResult::unresolveable().into());
+        }
+        Ok(ResolveResult::alternatives(results))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl GenerateSourceMap for SourceMapReference {
+    #[turbo_tasks::function]
+    async fn generate_source_map(&self) -> Result<Vc<OptionSourceMap>> {
+        match scan_source_mapping_url(self.from).await? {
+            Some(SourceMappingUrl::Inline(map)) => return Ok(OptionSourceMap(Some(map)).cell()),
+            Some(SourceMappingUrl::Relative(path)) => {
+                if let FileSystemEntryType::File = &*path.get_type().await? {
+                    return Ok(Vc::upcast::<Box<dyn GenerateSourceMap>>(FileSource::new(path))
+                        .generate_source_map());
+                }
+            }
+            None => {}
+        }
+
+        if let FileSystemEntryType::File = &*self.file.get_type().await? {
+            return Ok(
+                Vc::upcast::<Box<dyn GenerateSourceMap>>(FileSource::new(self.file))
+                    .generate_source_map(),
+            );
+        }
+
+        Ok(Vc::cell(None))
     }
 }
 
@@ -43,3 +234,139 @@ impl ValueToString for SourceMapReference {
         )))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_a_line_comment_directive() {
+        let text = "console.log(1);\n//# sourceMappingURL=app.js.map\n";
+        assert_eq!(find_source_mapping_url(text), Some("app.js.map"));
+    }
+
+    #[test]
+    fn finds_the_legacy_at_sign_directive() {
+        let text = "console.log(1);\n//@ sourceMappingURL=app.js.map";
+        assert_eq!(find_source_mapping_url(text), Some("app.js.map"));
+    }
+
+    #[test]
+    fn finds_a_block_comment_directive() {
+        let text = "console.log(1);\n/*# sourceMappingURL=app.js.map */\n";
+        assert_eq!(find_source_mapping_url(text), Some("app.js.map"));
+    }
+
+    #[test]
+    fn ignores_an_uncommented_occurrence() {
+        // The literal string appears inside what looks like a string/template, not a comment.
+        let text = r#"const s = "sourceMappingURL=app.js.map";"#;
+        assert_eq!(find_source_mapping_url(text), None);
+    }
+
+    #[test]
+    fn uses_the_last_directive_when_several_are_present() {
+        let text = "//# sourceMappingURL=first.js.map\ncode();\n//# sourceMappingURL=last.js.map";
+        assert_eq!(find_source_mapping_url(text), Some("last.js.map"));
+    }
+
+    #[test]
+    fn tail_str_never_splits_a_char() {
+        // "é" is a 2-byte UTF-8 sequence; asking for a 1-byte tail must skip forwards past it
+        // rather than panicking on a mid-character split.
+        // 1 byte can't land inside the 2-byte "é"; the boundary search has to round up to 2
+        // (the whole string), which with a 1-byte budget means there's nothing left to return.
+        let text = "é";
+        assert_eq!(tail_str(text, 1), "");
+        assert_eq!(tail_str(text, 0), "");
+        assert_eq!(tail_str(text, 2), "é");
+        assert_eq!(tail_str("hello world", 5), "world");
+    }
+
+    #[test]
+    fn decodes_base64() {
+        // "hello" base64-encoded, including padding.
+        assert_eq!(decode_base64("aGVsbG8=").unwrap(), b"hello");
+    }
+
+    #[test]
+    fn decodes_base64_without_padding() {
+        assert_eq!(decode_base64("aGVsbG8").unwrap(), b"hello");
+    }
+
+    #[test]
+    fn rejects_invalid_base64() {
+        assert!(decode_base64("not valid base64!!").is_err());
+    }
+
+    #[test]
+    fn decodes_percent_escapes() {
+        assert_eq!(decode_percent("a%20b%2Fc"), "a b/c");
+    }
+
+    #[test]
+    fn leaves_unescaped_bytes_alone() {
+        assert_eq!(decode_percent("plain-text"), "plain-text");
+    }
+
+    #[test]
+    fn does_not_panic_when_percent_escape_straddles_a_multibyte_char() {
+        // The byte after "%1" is the first byte of "é", a 2-byte UTF-8 sequence; a naive
+        // `&str` slice by raw byte offset would land mid-character and panic. Bytes 0x31 ('1')
+        // and the first byte of "é" (0xc3) aren't both ASCII hex digits, so this should just be
+        // passed through unescaped rather than panicking.
+        assert_eq!(decode_percent("%1é"), "%1é");
+    }
+
+    #[test]
+    fn leaves_a_truncated_percent_escape_at_the_end_alone() {
+        assert_eq!(decode_percent("abc%2"), "abc%2");
+        assert_eq!(decode_percent("abc%"), "abc%");
+    }
+
+    #[test]
+    fn decodes_an_inline_base64_data_url_end_to_end() {
+        // A minimal valid source map JSON, base64-encoded into a
+        // `data:application/json;base64,` directive at the tail of a generated file.
+        let json = r#"{"version":3,"sources":["a.js"],"names":[],"mappings":"AAAA"}"#;
+        let bytes = json.as_bytes();
+        let encoded = encode_base64_for_test(bytes);
+        let text = format!("code();\n//# sourceMappingURL=data:application/json;base64,{encoded}\n");
+        let url = find_source_mapping_url(&text).unwrap();
+        let payload = url.strip_prefix("data:application/json;base64,").unwrap();
+        let decoded = decode_base64(payload).unwrap();
+        assert_eq!(decoded, bytes);
+        let map = sourcemap::SourceMap::from_slice(&decoded).unwrap();
+        assert_eq!(map.get_source(0), Some("a.js"));
+    }
+
+    const BASE64_ALPHABET: &str =
+        "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    /// A tiny reference base64 encoder used only to build fixtures for the decoder tests above,
+    /// so the round-trip test doesn't depend on a hand-picked literal staying in sync with the
+    /// JSON fixture.
+    fn encode_base64_for_test(input: &[u8]) -> String {
+        let alphabet = BASE64_ALPHABET.as_bytes();
+        let mut out = String::with_capacity((input.len() + 2) / 3 * 4);
+        for chunk in input.chunks(3) {
+            let b0 = chunk[0] as u32;
+            let b1 = *chunk.get(1).unwrap_or(&0) as u32;
+            let b2 = *chunk.get(2).unwrap_or(&0) as u32;
+            let triple = (b0 << 16) | (b1 << 8) | b2;
+            out.push(alphabet[(triple >> 18 & 0x3f) as usize] as char);
+            out.push(alphabet[(triple >> 12 & 0x3f) as usize] as char);
+            out.push(if chunk.len() > 1 {
+                alphabet[(triple >> 6 & 0x3f) as usize] as char
+            } else {
+                '='
+            });
+            out.push(if chunk.len() > 2 {
+                alphabet[(triple & 0x3f) as usize] as char
+            } else {
+                '='
+            });
+        }
+        out
+    }
+}
@@ -8,9 +8,15 @@ use turbopack_core::{
     resolve::{origin::ResolveOrigin, parse::Request, ResolveResult},
 };
 
+use super::compose_cycles::check_compose_cycles;
 use crate::references::css_resolve;
 
 /// A `composes: ... from ...` CSS module reference.
+///
+/// Cycles formed by chains of these references (e.g. `a.module.css` composes from `b.module.css`
+/// which composes from `a.module.css`) are caught in [`resolve_reference`](
+/// AssetReference::resolve_reference) by walking the resolved `composes` graph through
+/// [`check_compose_cycles`].
 #[turbo_tasks::value]
 #[derive(Hash, Debug)]
 pub struct CssModuleComposeReference {
@@ -25,12 +31,18 @@ impl CssModuleComposeReference {
     pub fn new(origin: Vc<Box<dyn ResolveOrigin>>, request: Vc<Request>) -> Vc<Self> {
         Self::cell(CssModuleComposeReference { origin, request })
     }
-}
 
-#[turbo_tasks::value_impl]
-impl AssetReference for CssModuleComposeReference {
+    /// Resolves this reference's `composes` target, without checking for cycles.
+    ///
+    /// [`check_compose_cycles`]'s own graph walk follows every discovered `CssModuleComposeReference`
+    /// it finds, including this one's - if it resolved those through
+    /// [`AssetReference::resolve_reference`] (which itself calls [`check_compose_cycles`]), closing
+    /// a cycle would mean resolving this very reference's own `resolve_reference` task while it's
+    /// still on the call stack computing the walk, deadlocking on itself. Routing both the public
+    /// `resolve_reference` and the cycle walk through this plain resolver instead keeps cycle
+    /// detection from ever re-entering `AssetReference::resolve_reference`.
     #[turbo_tasks::function]
-    fn resolve_reference(&self) -> Vc<ResolveResult> {
+    pub fn resolve_composed_result(&self) -> Vc<ResolveResult> {
         css_resolve(
             self.origin,
             self.request,
@@ -43,6 +55,18 @@ impl AssetReference for CssModuleComposeReference {
     }
 }
 
+#[turbo_tasks::value_impl]
+impl AssetReference for CssModuleComposeReference {
+    #[turbo_tasks::function]
+    async fn resolve_reference(&self) -> Result<Vc<ResolveResult>> {
+        let result = self.resolve_composed_result();
+
+        check_compose_cycles(self.origin.origin_path(), result).await?;
+
+        Ok(result)
+    }
+}
+
 #[turbo_tasks::value_impl]
 impl ValueToString for CssModuleComposeReference {
     #[turbo_tasks::function]
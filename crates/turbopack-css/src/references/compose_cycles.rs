@@ -0,0 +1,422 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use turbo_tasks::{TryJoinIterExt, Vc};
+use turbo_tasks_fs::FileSystemPath;
+use turbopack_core::{
+    asset::Asset,
+    issue::{Issue, IssueExt, IssueSeverity},
+    module::Module,
+    resolve::ResolveResult,
+};
+
+use super::compose::CssModuleComposeReference;
+
+/// A single `composes: X from "..."` edge: the CSS module doing the composing, and the module it
+/// composes from.
+#[derive(Debug, Clone)]
+pub struct ComposeEdge<N> {
+    pub from: N,
+    pub to: N,
+}
+
+/// One strongly connected component of the compose graph. A component with more than one member,
+/// or a single member with a self-loop, is a composition cycle.
+#[derive(Debug, Clone)]
+pub struct ComposeScc<N> {
+    pub members: Vec<N>,
+    pub is_cycle: bool,
+}
+
+/// Finds the strongly connected components of the compose graph using an iterative form of
+/// Tarjan's algorithm (iterative so a long chain of `composes` can't blow the stack), and returns
+/// them in topological order (dependencies before dependents), giving a stable order to merge
+/// composed class names in.
+///
+/// Any edge that would close a cycle is left out of the condensed DAG - it's simply the edge that
+/// would, on its own, cause the cycle to be walked again - so the condensation always terminates.
+/// Callers should report an issue for every [`ComposeScc`] with `is_cycle` set.
+pub fn find_compose_cycles<N: Eq + std::hash::Hash + Clone>(
+    nodes: &[N],
+    edges: &[ComposeEdge<N>],
+) -> Vec<ComposeScc<N>> {
+    let mut adjacency: HashMap<&N, Vec<&N>> = HashMap::new();
+    for edge in edges {
+        adjacency.entry(&edge.from).or_default().push(&edge.to);
+    }
+
+    let mut tarjan = Tarjan {
+        adjacency,
+        index: HashMap::new(),
+        lowlink: HashMap::new(),
+        on_stack: HashMap::new(),
+        stack: Vec::new(),
+        next_index: 0,
+        sccs: Vec::new(),
+    };
+
+    for node in nodes {
+        if !tarjan.index.contains_key(node) {
+            tarjan.visit(node);
+        }
+    }
+
+    tarjan.sccs
+}
+
+struct Tarjan<'a, N: Eq + std::hash::Hash + Clone> {
+    adjacency: HashMap<&'a N, Vec<&'a N>>,
+    index: HashMap<&'a N, usize>,
+    lowlink: HashMap<&'a N, usize>,
+    on_stack: HashMap<&'a N, bool>,
+    stack: Vec<&'a N>,
+    next_index: usize,
+    sccs: Vec<ComposeScc<N>>,
+}
+
+impl<'a, N: Eq + std::hash::Hash + Clone> Tarjan<'a, N> {
+    /// Runs Tarjan's algorithm from `start`, using an explicit work stack of (node, next
+    /// successor to visit) frames to simulate the recursive formulation without recursing.
+    fn visit(&mut self, start: &'a N) {
+        let mut work: Vec<(&'a N, usize)> = vec![(start, 0)];
+        self.open(start);
+
+        while let Some(&mut (node, ref mut next_child)) = work.last_mut() {
+            let successors = self.adjacency.get(node).cloned().unwrap_or_default();
+            if *next_child < successors.len() {
+                let succ = successors[*next_child];
+                *next_child += 1;
+                if !self.index.contains_key(succ) {
+                    self.open(succ);
+                    work.push((succ, 0));
+                } else if *self.on_stack.get(succ).unwrap_or(&false) {
+                    let succ_index = self.index[succ];
+                    let lowlink = self.lowlink[node].min(succ_index);
+                    self.lowlink.insert(node, lowlink);
+                }
+                // Else: succ is already finished and off the stack, meaning it belongs to an
+                // SCC that's already been condensed. That edge can't create a new cycle.
+                continue;
+            }
+
+            // All of `node`'s successors have been visited: propagate its lowlink up to the
+            // caller frame, then, if `node` is the root of its SCC, pop the stack down to it.
+            work.pop();
+            if let Some(&(parent, _)) = work.last() {
+                let lowlink = self.lowlink[parent].min(self.lowlink[node]);
+                self.lowlink.insert(parent, lowlink);
+            }
+
+            if self.lowlink[node] == self.index[node] {
+                let self_loop = self
+                    .adjacency
+                    .get(node)
+                    .is_some_and(|succs| succs.contains(&node));
+
+                let mut members = Vec::new();
+                loop {
+                    let member = self.stack.pop().expect("node's own SCC is on the stack");
+                    self.on_stack.insert(member, false);
+                    members.push(member.clone());
+                    if member == node {
+                        break;
+                    }
+                }
+                let is_cycle = members.len() > 1 || self_loop;
+                self.sccs.push(ComposeScc { members, is_cycle });
+            }
+        }
+    }
+
+    fn open(&mut self, node: &'a N) {
+        self.index.insert(node, self.next_index);
+        self.lowlink.insert(node, self.next_index);
+        self.next_index += 1;
+        self.stack.push(node);
+        self.on_stack.insert(node, true);
+    }
+}
+
+/// Reports a `composes` cycle discovered by [`find_compose_cycles`].
+#[turbo_tasks::value(shared)]
+pub struct CssModuleComposeCycleIssue {
+    pub context: Vc<FileSystemPath>,
+    pub members: Vec<String>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for CssModuleComposeCycleIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> Vc<IssueSeverity> {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> Vc<String> {
+        Vc::cell("circular `composes` chain".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> Vc<String> {
+        Vc::cell("css".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> Vc<FileSystemPath> {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> Vc<String> {
+        Vc::cell(format!(
+            "these CSS modules compose from each other in a cycle, so class resolution order \
+             between them is undefined: {}",
+            self.members.join(", ")
+        ))
+    }
+}
+
+impl CssModuleComposeCycleIssue {
+    /// Emits an issue for every cyclic component found by [`find_compose_cycles`].
+    pub fn emit_all(context: Vc<FileSystemPath>, sccs: &[ComposeScc<String>]) {
+        for scc in sccs {
+            if !scc.is_cycle {
+                continue;
+            }
+            CssModuleComposeCycleIssue {
+                context,
+                members: scc.members.clone(),
+            }
+            .cell()
+            .emit();
+        }
+    }
+}
+
+/// The modules a single module's own [`CssModuleComposeReference`]s lead to.
+#[turbo_tasks::value(transparent)]
+struct ComposeSuccessors(Vec<Vc<Box<dyn Module>>>);
+
+/// Resolves the `composes` edges leading out of `module`, through
+/// [`CssModuleComposeReference::resolve_composed_result`] rather than
+/// [`AssetReference::resolve_reference`](turbopack_core::reference::AssetReference::resolve_reference)
+/// so this never re-enters [`check_compose_cycles`].
+///
+/// This is a `#[turbo_tasks::function]` (cached on `module`) precisely so that a module reachable
+/// from more than one `composes` chain - the common case, e.g. many modules composing from a
+/// handful of shared base classes - only has its own edges resolved once no matter how many
+/// different references' cycle checks end up walking through it, rather than being re-discovered
+/// from scratch by every one of them.
+#[turbo_tasks::function]
+async fn compose_successors(module: Vc<Box<dyn Module>>) -> Result<Vc<ComposeSuccessors>> {
+    let mut successors = Vec::new();
+    for reference in module.references().await?.iter() {
+        let Some(compose_ref) =
+            Vc::try_resolve_downcast_type::<CssModuleComposeReference>(*reference).await?
+        else {
+            continue;
+        };
+        let result = compose_ref.resolve_composed_result();
+        for asset in result.primary_assets().await?.iter() {
+            if let Some(next_module) = Vc::try_resolve_downcast::<Box<dyn Module>>(*asset).await? {
+                successors.push(next_module);
+            }
+        }
+    }
+    Ok(ComposeSuccessors(successors).cell())
+}
+
+/// Walks the `composes` graph starting at the edge `origin_path` -> (whatever `first_result`
+/// resolves to), following each resolved module's own `composes` edges through the memoized
+/// [`compose_successors`], and emits a [`CssModuleComposeCycleIssue`] for every cycle found whose
+/// lexicographically smallest member is `origin_path` (see [`is_cycle_emitter`]); other cycles
+/// found are left to that member's own call, so each cycle is reported once.
+/// Called as part of resolving a compose reference (passing that reference's own
+/// [`ResolveResult`] as `first_result`), so a cycle is caught as soon as its closing edge is
+/// resolved.
+pub async fn check_compose_cycles(
+    origin_path: Vc<FileSystemPath>,
+    first_result: Vc<ResolveResult>,
+) -> Result<()> {
+    let mut nodes = vec![origin_path];
+    let mut edges = Vec::new();
+    let mut seen = HashSet::new();
+    seen.insert(origin_path);
+
+    let mut frontier = Vec::new();
+    for asset in first_result.primary_assets().await?.iter() {
+        if let Some(module) = Vc::try_resolve_downcast::<Box<dyn Module>>(*asset).await? {
+            let to_path = module.ident().path();
+            edges.push(ComposeEdge {
+                from: origin_path,
+                to: to_path,
+            });
+            if seen.insert(to_path) {
+                nodes.push(to_path);
+                frontier.push((to_path, module));
+            }
+        }
+    }
+
+    while let Some((from_path, module)) = frontier.pop() {
+        for next_module in compose_successors(module).await?.iter().copied() {
+            let to_path = next_module.ident().path();
+            edges.push(ComposeEdge {
+                from: from_path,
+                to: to_path,
+            });
+
+            if seen.insert(to_path) {
+                nodes.push(to_path);
+                frontier.push((to_path, next_module));
+            }
+        }
+    }
+
+    let sccs = find_compose_cycles(&nodes, &edges);
+    if !sccs.iter().any(|scc| scc.is_cycle) {
+        return Ok(());
+    }
+
+    let named_sccs = sccs
+        .iter()
+        .filter(|scc| scc.is_cycle)
+        .map(|scc| async move {
+            let members = scc
+                .members
+                .iter()
+                .map(|path| async move { Ok::<_, anyhow::Error>(path.to_string().await?.to_string()) })
+                .try_join()
+                .await?;
+            Ok::<_, anyhow::Error>(ComposeScc {
+                members,
+                is_cycle: true,
+            })
+        })
+        .try_join()
+        .await?;
+
+    // `check_compose_cycles` is called once per `CssModuleComposeReference` in the cycle, and
+    // every one of them independently re-walks the same graph and rediscovers the same SCC -
+    // without deduping, an N-member cycle would emit N near-identical issues (one per member,
+    // each with a different `context`). `is_cycle_emitter` picks exactly one emitter per cycle.
+    let origin_path_str = origin_path.to_string().await?.to_string();
+    let deduped_sccs: Vec<_> = named_sccs
+        .into_iter()
+        .filter(|scc| is_cycle_emitter(&origin_path_str, &scc.members))
+        .collect();
+
+    CssModuleComposeCycleIssue::emit_all(origin_path, &deduped_sccs);
+    Ok(())
+}
+
+/// Whether `origin` should be the one to emit the issue for a cycle with the given `members`.
+///
+/// Every member's own call to [`check_compose_cycles`] independently rediscovers the same member
+/// set (traversal order doesn't matter - a cycle is reachable from any of its own members), so
+/// picking a single, deterministic member to emit from - the lexicographically smallest - avoids
+/// reporting the same cycle once per member.
+fn is_cycle_emitter(origin: &str, members: &[String]) -> bool {
+    members.iter().min().map(String::as_str) == Some(origin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(from: &'static str, to: &'static str) -> ComposeEdge<&'static str> {
+        ComposeEdge { from, to }
+    }
+
+    #[test]
+    fn no_cycle_in_a_dag() {
+        let nodes = ["a", "b", "c"];
+        let edges = [edge("a", "b"), edge("b", "c")];
+        let sccs = find_compose_cycles(&nodes, &edges);
+        assert!(sccs.iter().all(|scc| !scc.is_cycle));
+    }
+
+    #[test]
+    fn detects_a_three_node_cycle() {
+        let nodes = ["a", "b", "c"];
+        let edges = [edge("a", "b"), edge("b", "c"), edge("c", "a")];
+        let sccs = find_compose_cycles(&nodes, &edges);
+        let cycle = sccs
+            .iter()
+            .find(|scc| scc.is_cycle)
+            .expect("expected a cycle to be found");
+        let mut members = cycle.members.clone();
+        members.sort_unstable();
+        assert_eq!(members, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn detects_a_self_loop() {
+        let nodes = ["a"];
+        let edges = [edge("a", "a")];
+        let sccs = find_compose_cycles(&nodes, &edges);
+        assert!(sccs.iter().any(|scc| scc.is_cycle && scc.members == ["a"]));
+    }
+
+    #[test]
+    fn separates_independent_cycles() {
+        // a <-> b is a cycle, c -> d is not, and they don't share any nodes.
+        let nodes = ["a", "b", "c", "d"];
+        let edges = [edge("a", "b"), edge("b", "a"), edge("c", "d")];
+        let sccs = find_compose_cycles(&nodes, &edges);
+
+        let cyclic: Vec<_> = sccs.iter().filter(|scc| scc.is_cycle).collect();
+        assert_eq!(cyclic.len(), 1);
+        let mut members = cyclic[0].members.clone();
+        members.sort_unstable();
+        assert_eq!(members, ["a", "b"]);
+
+        assert!(sccs
+            .iter()
+            .filter(|scc| !scc.is_cycle)
+            .any(|scc| scc.members == ["c"]));
+        assert!(sccs
+            .iter()
+            .filter(|scc| !scc.is_cycle)
+            .any(|scc| scc.members == ["d"]));
+    }
+
+    #[test]
+    fn does_not_loop_forever_on_a_long_chain() {
+        // Regression test for the iterative DFS: a long chain used to be the case most likely to
+        // blow a recursive call stack. 10k nodes is enough to catch an accidental recursive
+        // re-introduction without making the test slow.
+        let count = 10_000;
+        let nodes: Vec<usize> = (0..count).collect();
+        let edges: Vec<ComposeEdge<usize>> =
+            (0..count - 1).map(|i| ComposeEdge { from: i, to: i + 1 }).collect();
+
+        let sccs = find_compose_cycles(&nodes, &edges);
+        assert!(sccs.iter().all(|scc| !scc.is_cycle));
+        assert_eq!(sccs.len(), count);
+    }
+
+    #[test]
+    fn only_the_lexicographically_smallest_member_emits() {
+        let members = ["b.module.css".to_string(), "a.module.css".to_string(), "c.module.css".to_string()];
+        assert!(is_cycle_emitter("a.module.css", &members));
+        assert!(!is_cycle_emitter("b.module.css", &members));
+        assert!(!is_cycle_emitter("c.module.css", &members));
+    }
+
+    #[test]
+    fn exactly_one_member_is_the_emitter() {
+        // Regardless of which member's own `check_compose_cycles` call rediscovers the cycle,
+        // exactly one of them should end up being the emitter, so the issue is reported once.
+        let members = [
+            "z.module.css".to_string(),
+            "a.module.css".to_string(),
+            "m.module.css".to_string(),
+        ];
+        let emitters = members
+            .iter()
+            .filter(|m| is_cycle_emitter(m, &members))
+            .count();
+        assert_eq!(emitters, 1);
+    }
+}